@@ -9,6 +9,8 @@ use libsignal_net_infra::route::ConnectError;
 use libsignal_net_infra::timeouts::TimeoutOr;
 use libsignal_net_infra::ws::{WebSocketConnectError, WebSocketServiceError};
 
+use crate::infra::certs::CertificateErrorSlot;
+use crate::infra::quic::QuicConnectError;
 use crate::ws::WebSocketServiceConnectError;
 
 #[derive(Debug, thiserror::Error, displaydoc::Display)]
@@ -29,6 +31,18 @@ pub enum ChatServiceError {
     IncomingDataInvalid,
     /// Request object must contain only ASCII text as header names and values.
     RequestHasInvalidHeader,
+    /// Server certificate has expired or is not yet valid
+    CertificateExpired,
+    /// Server certificate has been revoked
+    CertificateRevoked,
+    /// Server certificate was issued by an unknown or untrusted authority
+    UntrustedCertificateAuthority,
+    /// Server certificate is not valid for the requested hostname
+    CertificateHostnameMismatch,
+    /// Server certificate is invalid
+    CertificateInvalid,
+    /// QUIC transport failed
+    QuicTransportFailed,
     /// Timeout
     Timeout,
     /// Timed out while establishing connection after {attempts} attempts
@@ -48,8 +62,14 @@ pub enum ChatServiceError {
 impl ChatServiceError {
     pub fn from_single_connect_error(
         e: TimeoutOr<ConnectError<WebSocketServiceConnectError>>,
+        error_slot: &CertificateErrorSlot,
     ) -> Self {
         use crate::infra::route::ConnectError;
+        // Drain any certificate reason recorded during this connect regardless of the outcome: the
+        // slot is shared across attempts on the same connector, so a reason left behind by an
+        // attempt that surfaced as `AllAttemptsFailed` must not leak into a later `FatalConnect`
+        // and be misclassified as a certificate error.
+        let certificate_error = error_slot.take();
         match e {
             TimeoutOr::Other(ConnectError::NoResolvedRoutes) => {
                 ChatServiceError::AllConnectionRoutesFailed { attempts: 0 }
@@ -57,43 +77,94 @@ impl ChatServiceError {
             TimeoutOr::Other(ConnectError::AllAttemptsFailed) => {
                 ChatServiceError::AllConnectionRoutesFailed { attempts: 1 }
             }
-            TimeoutOr::Other(ConnectError::FatalConnect(err)) => err.into(),
+            TimeoutOr::Other(ConnectError::FatalConnect(err)) => {
+                // A TLS certificate rejection reaches us here as an opaque transport error, but the
+                // platform verifier stashed the precise reason into the shared slot just before it
+                // returned the alert. Prefer that granular classification when it's present; the
+                // slot is only ever populated by a failed certificate verification.
+                match certificate_error {
+                    Some(error) => Self::from_certificate_error(error),
+                    None => err.into(),
+                }
+            }
             TimeoutOr::Timeout {
                 attempt_duration: _,
             } => ChatServiceError::TimeoutEstablishingConnection { attempts: 1 },
         }
     }
+
+    /// Classifies a certificate verification failure into a granular error for the app.
+    ///
+    /// The reason is the [`rustls::CertificateError`] the platform verifier computed before
+    /// BoringSSL collapsed it into a TLS alert; see [`CertificateErrorSlot`].
+    ///
+    /// [`CertificateErrorSlot`]: crate::infra::certs::CertificateErrorSlot
+    pub(crate) fn from_certificate_error(error: rustls::CertificateError) -> Self {
+        match error {
+            rustls::CertificateError::Expired | rustls::CertificateError::NotValidYet => {
+                Self::CertificateExpired
+            }
+            rustls::CertificateError::Revoked => Self::CertificateRevoked,
+            rustls::CertificateError::UnknownIssuer => Self::UntrustedCertificateAuthority,
+            rustls::CertificateError::NotValidForName => Self::CertificateHostnameMismatch,
+            _ => Self::CertificateInvalid,
+        }
+    }
 }
 
 impl LogSafeDisplay for ChatServiceError {}
 
+impl From<QuicConnectError> for ChatServiceError {
+    /// Classifies a failure establishing the QUIC transport into a chat error.
+    ///
+    /// The buckets mirror [`from_single_connect_error`](Self::from_single_connect_error) so the
+    /// route selector can fall back between QUIC and WebSocket: a setup failure before any packet
+    /// leaves the client means no route was usable, a handshake/idle timeout maps to
+    /// [`Timeout`](Self::Timeout), and everything else surfaces as the dedicated
+    /// [`QuicTransportFailed`](Self::QuicTransportFailed) variant.
+    fn from(e: QuicConnectError) -> Self {
+        match e {
+            // Setup failed before any packet was sent: there is no usable route here.
+            QuicConnectError::Connect(_) => Self::AllConnectionRoutesFailed { attempts: 1 },
+            QuicConnectError::Connection(quinn::ConnectionError::TimedOut) => Self::Timeout,
+            QuicConnectError::Connection(_) => Self::QuicTransportFailed,
+        }
+    }
+}
+
 impl From<WebSocketServiceConnectError> for ChatServiceError {
     fn from(e: WebSocketServiceConnectError) -> Self {
         match e {
             WebSocketServiceConnectError::Connect(e, _) => match e {
                 WebSocketConnectError::Transport(e) => match e {
                     TransportConnectError::InvalidConfiguration => {
-                        WebSocketServiceError::Other("invalid configuration")
+                        WebSocketServiceError::Other("invalid configuration").into()
                     }
                     TransportConnectError::TcpConnectionFailed => {
-                        WebSocketServiceError::Other("TCP connection failed")
+                        WebSocketServiceError::Other("TCP connection failed").into()
+                    }
+                    TransportConnectError::DnsError => {
+                        WebSocketServiceError::Other("DNS error").into()
                     }
-                    TransportConnectError::DnsError => WebSocketServiceError::Other("DNS error"),
                     TransportConnectError::SslError(_)
                     | TransportConnectError::SslFailedHandshake(_) => {
-                        WebSocketServiceError::Other("TLS failure")
+                        // The handshake failed at the TLS layer. When the cause was a rejected
+                        // certificate, the precise reason is read back from the verifier's slot in
+                        // `from_single_connect_error`; this conversion is the generic fallback for
+                        // everything else (protocol/version/cipher mismatch, a non-cert alert, a
+                        // reset mid-handshake, etc.).
+                        WebSocketServiceError::Other("TLS failure").into()
                     }
                     TransportConnectError::CertError => {
-                        WebSocketServiceError::Other("failed to load certificates")
+                        WebSocketServiceError::Other("failed to load certificates").into()
                     }
                     TransportConnectError::ProxyProtocol => {
-                        WebSocketServiceError::Other("proxy protocol error")
+                        WebSocketServiceError::Other("proxy protocol error").into()
                     }
                     TransportConnectError::ClientAbort => {
-                        WebSocketServiceError::Other("client abort error")
+                        WebSocketServiceError::Other("client abort error").into()
                     }
-                }
-                .into(),
+                },
                 WebSocketConnectError::Timeout => Self::Timeout,
                 WebSocketConnectError::WebSocketError(e) => Self::WebSocket(e.into()),
             },
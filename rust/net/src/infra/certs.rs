@@ -4,21 +4,48 @@
 //
 
 use std::borrow::Cow;
+use std::sync::{Arc, Mutex};
 
 use boring::error::ErrorStack;
-use boring::ssl::{SslAlert, SslConnectorBuilder, SslVerifyError, SslVerifyMode};
-use boring::x509::store::X509StoreBuilder;
+use boring::pkey::PKey;
+use boring::ssl::{SslAlert, SslConnectorBuilder, SslVerifyError, SslVerifyMode, StatusType};
 use boring::x509::X509;
-use rustls::client::danger::ServerCertVerifier;
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::pki_types::{CertificateDer, CertificateRevocationListDer, ServerName, UnixTime};
+use rustls::{DigitallySignedStruct, RootCertStore, SignatureScheme};
+use sha2::{Digest as _, Sha256};
 
 const SIGNAL_ROOT_CERT_DER: &[u8] = include_bytes!("../../res/signal.cer");
 
+/// Shared slot used to carry the precise [`rustls::CertificateError`] out of the custom-verify
+/// callback.
+///
+/// BoringSSL only lets the callback signal success or a coarse [`SslAlert`], so the handshake
+/// failure reported to the connecting task loses the reason rustls computed. The connector stashes
+/// the mapped error here before returning [`SslVerifyError::Invalid`]; the caller reads it back once
+/// the handshake fails to produce a granular error for the app.
+#[derive(Clone, Debug, Default)]
+pub struct CertificateErrorSlot(Arc<Mutex<Option<rustls::CertificateError>>>);
+
+impl CertificateErrorSlot {
+    fn store(&self, error: rustls::CertificateError) {
+        *self.0.lock().expect("not poisoned") = Some(error);
+    }
+
+    /// Takes the certificate error recorded during the most recent failed handshake, if any.
+    pub fn take(&self) -> Option<rustls::CertificateError> {
+        self.0.lock().expect("not poisoned").take()
+    }
+}
+
 #[derive(thiserror::Error, Debug, displaydoc::Display)]
 pub enum Error {
     /// Bad certificate
     BadCertificate,
     /// Bad hostname
     BadHostname,
+    /// Bad client identity
+    BadClientIdentity,
 }
 
 impl From<ErrorStack> for Error {
@@ -30,8 +57,21 @@ impl From<ErrorStack> for Error {
 #[derive(Debug, Clone)]
 pub enum RootCertificates {
     Native,
-    Signal,
-    FromDer(Cow<'static, [u8]>),
+    Signal {
+        /// Certificate revocation lists enforced offline against the pinned anchor.
+        crls: Vec<CertificateRevocationListDer<'static>>,
+    },
+    FromDer {
+        certificate: Cow<'static, [u8]>,
+        /// Certificate revocation lists enforced offline against `certificate`.
+        crls: Vec<CertificateRevocationListDer<'static>>,
+    },
+    /// Pins the server by the SHA-256 of a presented certificate's SubjectPublicKeyInfo.
+    ///
+    /// At least one certificate in the presented chain must hash to one of these pins. Pinning the
+    /// SPKI rather than the whole certificate survives renewals that keep the same key. Name and
+    /// validity are still checked, against the presented chain rather than a CA.
+    PinnedKeys(Vec<[u8; 32]>),
 }
 
 impl RootCertificates {
@@ -39,21 +79,237 @@ impl RootCertificates {
         &self,
         connector: &mut SslConnectorBuilder,
         host_name: &str,
+        error_slot: &CertificateErrorSlot,
     ) -> Result<(), Error> {
-        let der = match self {
-            RootCertificates::Native => {
-                return set_up_platform_verifier(
-                    connector,
-                    host_name,
-                    rustls_platform_verifier::Verifier::new(),
-                );
+        set_up_platform_verifier(connector, host_name, self.build_verifier(error_slot)?)
+    }
+
+    /// Builds the certificate verifier used for the WebSocket/TCP route, for reuse by transports
+    /// that take a rustls [`ServerCertVerifier`] directly (e.g. QUIC).
+    ///
+    /// The verifier honors all three trust modes and, for [`RootCertificates::PinnedKeys`], the
+    /// configured SPKI pins. Failures are recorded into `error_slot` for later classification.
+    pub fn server_cert_verifier(
+        &self,
+        error_slot: &CertificateErrorSlot,
+    ) -> Result<Arc<dyn ServerCertVerifier>, Error> {
+        Ok(Arc::new(self.build_verifier(error_slot)?))
+    }
+
+    fn build_verifier(
+        &self,
+        error_slot: &CertificateErrorSlot,
+    ) -> Result<SignalServerCertVerifier, Error> {
+        let (inner, spki_pins): (Option<Arc<dyn ServerCertVerifier>>, Vec<[u8; 32]>) = match self {
+            RootCertificates::Native => (
+                Some(Arc::new(rustls_platform_verifier::Verifier::new())),
+                Vec::new(),
+            ),
+            RootCertificates::PinnedKeys(pins) => {
+                // An empty pin set would accept any certificate; fail closed rather than silently
+                // degrading to unpinned verification.
+                if pins.is_empty() {
+                    return Err(Error::BadCertificate);
+                }
+                // Trust is established by the SPKI pin, not by chaining to a CA, so there is no
+                // inner verifier: name/validity are checked against the presented chain itself (see
+                // [`SignalServerCertVerifier::verify_server_cert`]).
+                (None, pins.clone())
+            }
+            RootCertificates::Signal { crls } => (
+                Some(webpki_verifier(SIGNAL_ROOT_CERT_DER, crls)?),
+                Vec::new(),
+            ),
+            RootCertificates::FromDer { certificate, crls } => {
+                (Some(webpki_verifier(certificate, crls)?), Vec::new())
             }
-            RootCertificates::Signal => SIGNAL_ROOT_CERT_DER,
-            RootCertificates::FromDer(der) => der,
         };
-        let mut store_builder = X509StoreBuilder::new()?;
-        store_builder.add_cert(X509::from_der(der)?)?;
-        connector.set_verify_cert_store(store_builder.build())?;
+        Ok(SignalServerCertVerifier {
+            inner,
+            spki_pins,
+            error_slot: error_slot.clone(),
+        })
+    }
+}
+
+/// Builds a [`rustls::client::WebPkiServerVerifier`] pinned to a single DER anchor.
+///
+/// Verifying against the pinned anchor with rustls rather than BoringSSL's own store lets the CRLs
+/// be enforced offline.
+fn webpki_verifier(
+    der: &[u8],
+    crls: &[CertificateRevocationListDer<'static>],
+) -> Result<Arc<dyn ServerCertVerifier>, Error> {
+    let mut roots = RootCertStore::empty();
+    roots
+        .add(CertificateDer::from(der.to_vec()))
+        .map_err(|_| Error::BadCertificate)?;
+    let verifier = rustls::client::WebPkiServerVerifier::builder(roots.into())
+        .with_crls(crls.iter().cloned())
+        .build()
+        .map_err(|_| Error::BadCertificate)?;
+    Ok(verifier)
+}
+
+/// Builds a name/validity verifier anchored on the root of a presented chain.
+///
+/// Used by [`RootCertificates::PinnedKeys`], where the SPKI pin rather than a CA establishes
+/// trust: the topmost presented certificate is treated as the trust anchor, so the chain is only
+/// checked for internal consistency, validity period, and hostname. A single self-signed leaf is
+/// its own anchor.
+fn presented_chain_verifier(
+    end_entity: &CertificateDer<'_>,
+    intermediates: &[CertificateDer<'_>],
+) -> Result<Arc<dyn ServerCertVerifier>, rustls::Error> {
+    let anchor = intermediates.last().unwrap_or(end_entity);
+    let mut roots = RootCertStore::empty();
+    roots
+        .add(anchor.clone())
+        .map_err(|_| rustls::Error::InvalidCertificate(rustls::CertificateError::BadEncoding))?;
+    rustls::client::WebPkiServerVerifier::builder(roots.into())
+        .build()
+        .map_err(|e| rustls::Error::General(e.to_string()))
+}
+
+/// The certificate verifier shared by every transport.
+///
+/// Wraps a base verifier (the platform verifier, or a [`rustls::client::WebPkiServerVerifier`]
+/// pinned to a single anchor) or, for [`RootCertificates::PinnedKeys`], SPKI pinning alone, and
+/// records the precise failure reason into a [`CertificateErrorSlot`] so it can be surfaced to the
+/// app.
+#[derive(Debug)]
+struct SignalServerCertVerifier {
+    /// The base verifier that establishes trust by chaining to a CA, or `None` for
+    /// [`RootCertificates::PinnedKeys`], where the SPKI pin is what establishes trust and only
+    /// name/validity are checked against the presented chain.
+    inner: Option<Arc<dyn ServerCertVerifier>>,
+    spki_pins: Vec<[u8; 32]>,
+    error_slot: CertificateErrorSlot,
+}
+
+impl ServerCertVerifier for SignalServerCertVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        server_name: &ServerName<'_>,
+        ocsp_response: &[u8],
+        now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        // If public-key pins are configured, require at least one presented certificate's
+        // SubjectPublicKeyInfo to match before running the usual name/validity checks.
+        if !self.spki_pins.is_empty()
+            && !std::iter::once(end_entity)
+                .chain(intermediates)
+                .any(|cert| spki_matches_pin(cert, &self.spki_pins))
+        {
+            let reason = rustls::CertificateError::ApplicationVerificationFailure;
+            self.error_slot.store(reason.clone());
+            return Err(rustls::Error::InvalidCertificate(reason));
+        }
+
+        // For pinned-key trust there is no configured CA to chain to; build a name/validity
+        // verifier anchored on the root of the presented chain so renewals that keep the same key
+        // (and so still match a pin) are accepted regardless of any public CA.
+        let pinned;
+        let verifier: &dyn ServerCertVerifier = match &self.inner {
+            Some(inner) => inner.as_ref(),
+            None => {
+                pinned = presented_chain_verifier(end_entity, intermediates)?;
+                pinned.as_ref()
+            }
+        };
+
+        verifier
+            .verify_server_cert(end_entity, intermediates, server_name, ocsp_response, now)
+            .inspect_err(|e| {
+                if let rustls::Error::InvalidCertificate(reason) = e {
+                    self.error_slot.store(reason.clone());
+                }
+            })
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.inner.verify_tls12_signature(message, cert, dss)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.inner.verify_tls13_signature(message, cert, dss)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.inner.supported_verify_schemes()
+    }
+}
+
+/// Returns whether the SHA-256 of `cert`'s SubjectPublicKeyInfo matches one of `pins`.
+fn spki_matches_pin(cert: &CertificateDer<'_>, pins: &[[u8; 32]]) -> bool {
+    let Ok(spki) = X509::from_der(cert).and_then(|x509| x509.public_key()?.public_key_to_der())
+    else {
+        return false;
+    };
+    let digest: [u8; 32] = Sha256::digest(&spki).into();
+    pins.contains(&digest)
+}
+
+/// A client certificate and key presented during the TLS handshake for mutual TLS.
+///
+/// Used to authenticate to enterprise proxies or gateways that require a client certificate (e.g.
+/// SASL-EXTERNAL-style links). Apply it alongside [`RootCertificates::apply_to_connector`].
+#[derive(Clone)]
+pub struct ClientIdentity {
+    /// DER-encoded certificate chain, leaf first.
+    pub cert_chain_der: Vec<Cow<'static, [u8]>>,
+    /// DER-encoded private key for the leaf certificate.
+    pub private_key_der: zeroize::Zeroizing<Vec<u8>>,
+}
+
+impl std::fmt::Debug for ClientIdentity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Don't print the private key material.
+        f.debug_struct("ClientIdentity")
+            .field("cert_chain_der", &self.cert_chain_der)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ClientIdentity {
+    /// Loads the certificate chain and private key into `connector`.
+    ///
+    /// Fails with [`Error::BadClientIdentity`] if the chain is empty, any component fails to
+    /// decode, or the key does not match the leaf certificate.
+    pub fn apply_to_connector(&self, connector: &mut SslConnectorBuilder) -> Result<(), Error> {
+        let mut chain = self.cert_chain_der.iter();
+        let leaf = chain.next().ok_or(Error::BadClientIdentity)?;
+        let leaf = X509::from_der(leaf).map_err(|_| Error::BadClientIdentity)?;
+        connector
+            .set_certificate(&leaf)
+            .map_err(|_| Error::BadClientIdentity)?;
+        for intermediate in chain {
+            let cert = X509::from_der(intermediate).map_err(|_| Error::BadClientIdentity)?;
+            connector
+                .add_extra_chain_cert(cert)
+                .map_err(|_| Error::BadClientIdentity)?;
+        }
+
+        let key =
+            PKey::private_key_from_der(&self.private_key_der).map_err(|_| Error::BadClientIdentity)?;
+        connector
+            .set_private_key(&key)
+            .map_err(|_| Error::BadClientIdentity)?;
+        connector
+            .check_private_key()
+            .map_err(|_| Error::BadClientIdentity)?;
         Ok(())
     }
 }
@@ -63,12 +319,15 @@ impl RootCertificates {
 fn set_up_platform_verifier(
     connector: &mut SslConnectorBuilder,
     host_name: &str,
-    verifier: impl ServerCertVerifier + 'static,
+    verifier: SignalServerCertVerifier,
 ) -> Result<(), Error> {
     let host_as_server_name = rustls::pki_types::ServerName::try_from(host_name)
         .map_err(|_| Error::BadHostname)?
         .to_owned();
 
+    // Ask the server to staple an OCSP response so the verifier can check revocation itself.
+    connector.set_status_type(StatusType::OCSP);
+
     connector.set_custom_verify_callback(SslVerifyMode::PEER, move |ssl| {
         // Get the certificate chain, lazily convert each certificate to DER (as expected by rustls).
         let mut cert_chain = ssl
@@ -90,65 +349,25 @@ fn set_up_platform_verifier(
             .collect::<Result<_, boring::error::ErrorStack>>()
             .map_err(|_| SslVerifyError::Invalid(SslAlert::BAD_CERTIFICATE))?;
 
-        // We don't do our own OCSP. Either the platform will do its own checks, or it won't.
-        let ocsp_responses = [];
+        // Hand the stapled OCSP response (if the server provided one) to the verifier. The
+        // platform verifier can factor it into revocation checking; the pinned-trust path relies
+        // instead on the CRLs configured on the `WebPkiServerVerifier`.
+        let ocsp_response = ssl.ocsp_response().unwrap_or(&[]);
 
         verifier
             .verify_server_cert(
                 &end_entity,
                 &intermediates,
                 &host_as_server_name,
-                &ocsp_responses,
+                ocsp_response,
                 rustls::pki_types::UnixTime::now(),
             )
             .map_err(|e| {
-                // The most important thing is to reject the certificate. Mapping the errors over
-                // only affects what message gets reported in logs. Which isn't *unimportant*, but
-                // isn't critical for correctness either.
-                //
-                // From RFC 5246:
-                // - bad_certificate: A certificate was corrupt, contained signatures that did not
-                //   verify correctly, etc.
-                // - certificate_expired: A certificate has expired or is not currently valid.
-                // - certificate_unknown: Some other (unspecified) issue arose in processing the
-                //   certificate, rendering it unacceptable.
-                // - certificate_revoked: A certificate was revoked by its signer.
-                // - unknown_ca: A valid certificate chain or partial chain was received, but the
-                //   certificate was not accepted because the CA certificate could not be located or
-                //   couldn't be matched with a known, trusted CA.
-                // - internal_error: An internal error unrelated to the peer or the correctness of
-                //   the protocol (such as a memory allocation failure) makes it impossible to
-                //   continue.
                 log::debug!(
                     "TLS certificate for {} failed verification: {e}",
                     host_as_server_name.to_str()
                 );
-                SslVerifyError::Invalid(match e {
-                    rustls::Error::InvalidCertificate(e) => match e {
-                        rustls::CertificateError::BadEncoding => SslAlert::BAD_CERTIFICATE,
-                        rustls::CertificateError::Expired => SslAlert::CERTIFICATE_EXPIRED,
-                        rustls::CertificateError::NotValidYet => SslAlert::CERTIFICATE_UNKNOWN,
-                        rustls::CertificateError::Revoked => SslAlert::CERTIFICATE_REVOKED,
-                        rustls::CertificateError::UnhandledCriticalExtension => {
-                            SslAlert::CERTIFICATE_UNKNOWN
-                        }
-                        rustls::CertificateError::UnknownIssuer => SslAlert::UNKNOWN_CA,
-                        rustls::CertificateError::UnknownRevocationStatus => {
-                            SslAlert::CERTIFICATE_UNKNOWN
-                        }
-                        rustls::CertificateError::BadSignature => SslAlert::BAD_CERTIFICATE,
-                        rustls::CertificateError::NotValidForName => SslAlert::CERTIFICATE_UNKNOWN,
-                        rustls::CertificateError::InvalidPurpose => SslAlert::CERTIFICATE_UNKNOWN,
-                        rustls::CertificateError::ApplicationVerificationFailure => {
-                            SslAlert::INTERNAL_ERROR
-                        }
-                        rustls::CertificateError::Other(_) => SslAlert::CERTIFICATE_UNKNOWN,
-
-                        // CertificateError is marked non_exhaustive, so we also have to have an explicit fallback:
-                        _ => SslAlert::CERTIFICATE_UNKNOWN,
-                    },
-                    _ => SslAlert::BAD_CERTIFICATE,
-                })
+                SslVerifyError::Invalid(certificate_alert(&e))
             })?;
 
         Ok(())
@@ -157,13 +376,55 @@ fn set_up_platform_verifier(
     Ok(())
 }
 
+/// Maps a rustls verification error onto the BoringSSL [`SslAlert`] sent to the peer.
+///
+/// The most important thing is to reject the certificate; the alert only affects what gets
+/// reported in logs and to the peer. Which isn't *unimportant*, but isn't critical for correctness
+/// either.
+///
+/// From RFC 5246:
+/// - bad_certificate: A certificate was corrupt, contained signatures that did not verify
+///   correctly, etc.
+/// - certificate_expired: A certificate has expired or is not currently valid.
+/// - certificate_unknown: Some other (unspecified) issue arose in processing the certificate,
+///   rendering it unacceptable.
+/// - certificate_revoked: A certificate was revoked by its signer.
+/// - unknown_ca: A valid certificate chain or partial chain was received, but the certificate was
+///   not accepted because the CA certificate could not be located or couldn't be matched with a
+///   known, trusted CA.
+/// - internal_error: An internal error unrelated to the peer or the correctness of the protocol
+///   (such as a memory allocation failure) makes it impossible to continue.
+fn certificate_alert(error: &rustls::Error) -> SslAlert {
+    let rustls::Error::InvalidCertificate(error) = error else {
+        return SslAlert::BAD_CERTIFICATE;
+    };
+    match error {
+        rustls::CertificateError::BadEncoding => SslAlert::BAD_CERTIFICATE,
+        rustls::CertificateError::Expired => SslAlert::CERTIFICATE_EXPIRED,
+        rustls::CertificateError::NotValidYet => SslAlert::CERTIFICATE_UNKNOWN,
+        rustls::CertificateError::Revoked => SslAlert::CERTIFICATE_REVOKED,
+        rustls::CertificateError::UnhandledCriticalExtension => SslAlert::CERTIFICATE_UNKNOWN,
+        rustls::CertificateError::UnknownIssuer => SslAlert::UNKNOWN_CA,
+        rustls::CertificateError::UnknownRevocationStatus => SslAlert::CERTIFICATE_UNKNOWN,
+        rustls::CertificateError::BadSignature => SslAlert::BAD_CERTIFICATE,
+        rustls::CertificateError::NotValidForName => SslAlert::CERTIFICATE_UNKNOWN,
+        rustls::CertificateError::InvalidPurpose => SslAlert::CERTIFICATE_UNKNOWN,
+        // Our own verifier rejected the certificate (e.g. an SPKI pin mismatch): that's a rejected
+        // peer certificate, not an internal fault, so report bad_certificate rather than
+        // internal_error.
+        rustls::CertificateError::ApplicationVerificationFailure => SslAlert::BAD_CERTIFICATE,
+        rustls::CertificateError::Other(_) => SslAlert::CERTIFICATE_UNKNOWN,
+
+        // CertificateError is marked non_exhaustive, so we also have to have an explicit fallback:
+        _ => SslAlert::CERTIFICATE_UNKNOWN,
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use std::sync::Arc;
-
     use assert_matches::assert_matches;
     use boring::ssl::{ErrorCode, SslConnector, SslMethod};
-    use rustls::RootCertStore;
+    use boring::x509::X509;
     use tokio::net::TcpStream;
 
     use crate::infra::tcp_ssl::testutil::{
@@ -178,20 +439,12 @@ mod test {
         let (addr, server) = localhost_http_server();
         let _server_handle = tokio::spawn(server);
 
-        let mut root_cert_store = RootCertStore::empty();
-        root_cert_store
-            .add(SERVER_CERTIFICATE.cert.der().clone())
-            .expect("valid");
-        let verifier = rustls::client::WebPkiServerVerifier::builder(Arc::new(root_cert_store))
-            .build()
-            .expect("valid");
-
         let mut ssl = SslConnector::builder(SslMethod::tls_client()).expect("valid");
-        set_up_platform_verifier(
-            &mut ssl,
-            SERVER_HOSTNAME,
-            Arc::into_inner(verifier).expect("only one referent"),
-        )
+        RootCertificates::FromDer {
+            certificate: SERVER_CERTIFICATE.cert.der().to_vec().into(),
+            crls: Vec::new(),
+        }
+        .apply_to_connector(&mut ssl, SERVER_HOSTNAME, &CertificateErrorSlot::default())
         .expect("valid");
 
         let transport = TcpStream::connect(addr).await.expect("can connect");
@@ -211,22 +464,79 @@ mod test {
         let (addr, server) = localhost_http_server();
         let _server_handle = tokio::spawn(server);
 
-        let mut root_cert_store = RootCertStore::empty();
+        let mut ssl = SslConnector::builder(SslMethod::tls_client()).expect("valid");
+        let error_slot = CertificateErrorSlot::default();
         // Wrong certificate here!
-        root_cert_store
-            .add(PROXY_CERTIFICATE.cert.der().clone())
-            .expect("valid");
-        let verifier = rustls::client::WebPkiServerVerifier::builder(Arc::new(root_cert_store))
-            .build()
+        RootCertificates::FromDer {
+            certificate: PROXY_CERTIFICATE.cert.der().to_vec().into(),
+            crls: Vec::new(),
+        }
+        .apply_to_connector(&mut ssl, SERVER_HOSTNAME, &error_slot)
+        .expect("valid");
+
+        let transport = TcpStream::connect(addr).await.expect("can connect");
+        assert_matches!(
+            tokio_boring::connect(
+                ssl.build().configure().expect("valid"),
+                SERVER_HOSTNAME,
+                transport,
+            )
+            .await,
+            Err(e) if e.code() == Some(ErrorCode::SSL)
+        );
+
+        // The precise reason is stashed so the handshake failure can be classified.
+        assert_matches!(
+            error_slot.take(),
+            Some(rustls::CertificateError::UnknownIssuer)
+        );
+    }
+
+    /// SHA-256 of the SubjectPublicKeyInfo of a DER-encoded certificate.
+    fn spki_pin(cert_der: &[u8]) -> [u8; 32] {
+        let spki = X509::from_der(cert_der)
+            .expect("valid")
+            .public_key()
+            .expect("has key")
+            .public_key_to_der()
             .expect("valid");
+        Sha256::digest(&spki).into()
+    }
 
+    #[tokio::test]
+    async fn verify_certificate_via_spki_pin() {
+        let (addr, server) = localhost_http_server();
+        let _server_handle = tokio::spawn(server);
+
+        let pin = spki_pin(SERVER_CERTIFICATE.cert.der());
         let mut ssl = SslConnector::builder(SslMethod::tls_client()).expect("valid");
-        set_up_platform_verifier(
-            &mut ssl,
+        RootCertificates::PinnedKeys(vec![pin])
+            .apply_to_connector(&mut ssl, SERVER_HOSTNAME, &CertificateErrorSlot::default())
+            .expect("valid");
+
+        let transport = TcpStream::connect(addr).await.expect("can connect");
+        let connection = tokio_boring::connect(
+            ssl.build().configure().expect("valid"),
             SERVER_HOSTNAME,
-            Arc::into_inner(verifier).expect("only one referent"),
+            transport,
         )
-        .expect("valid");
+        .await
+        .expect("successful handshake");
+
+        make_http_request_response_over(connection).await;
+    }
+
+    #[tokio::test]
+    async fn verify_certificate_failure_via_spki_pin() {
+        let (addr, server) = localhost_http_server();
+        let _server_handle = tokio::spawn(server);
+
+        // Pin the wrong key: the presented chain should match none of the pins.
+        let pin = spki_pin(PROXY_CERTIFICATE.cert.der());
+        let mut ssl = SslConnector::builder(SslMethod::tls_client()).expect("valid");
+        RootCertificates::PinnedKeys(vec![pin])
+            .apply_to_connector(&mut ssl, SERVER_HOSTNAME, &CertificateErrorSlot::default())
+            .expect("valid");
 
         let transport = TcpStream::connect(addr).await.expect("can connect");
         assert_matches!(
@@ -239,4 +549,30 @@ mod test {
             Err(e) if e.code() == Some(ErrorCode::SSL)
         );
     }
+
+    #[test]
+    fn client_identity_rejects_unparseable_private_key() {
+        let mut ssl = SslConnector::builder(SslMethod::tls_client()).expect("valid");
+        let identity = ClientIdentity {
+            cert_chain_der: vec![SERVER_CERTIFICATE.cert.der().to_vec().into()],
+            private_key_der: b"not a private key".to_vec().into(),
+        };
+        assert_matches!(
+            identity.apply_to_connector(&mut ssl),
+            Err(Error::BadClientIdentity)
+        );
+    }
+
+    #[test]
+    fn client_identity_rejects_empty_chain() {
+        let mut ssl = SslConnector::builder(SslMethod::tls_client()).expect("valid");
+        let identity = ClientIdentity {
+            cert_chain_der: vec![],
+            private_key_der: Vec::new().into(),
+        };
+        assert_matches!(
+            identity.apply_to_connector(&mut ssl),
+            Err(Error::BadClientIdentity)
+        );
+    }
 }
@@ -0,0 +1,7 @@
+//
+// Copyright 2023 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+pub mod certs;
+pub mod quic;
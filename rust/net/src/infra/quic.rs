@@ -0,0 +1,87 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! QUIC transport built on [quinn], reusing the Signal certificate verifier.
+//!
+//! QUIC's 0-RTT/1-RTT handshake and connection migration make it a useful alternative to the
+//! WebSocket/TCP+TLS route on flaky mobile networks. Trust is delegated to the exact same
+//! [`ServerCertVerifier`] the WebSocket route installs (see [`super::certs`]), so every
+//! [`RootCertificates`] mode behaves identically across transports.
+//!
+//! [`ServerCertVerifier`]: rustls::client::danger::ServerCertVerifier
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use quinn::crypto::rustls::QuicClientConfig;
+
+use crate::infra::certs::{CertificateErrorSlot, Error, RootCertificates};
+
+/// ALPN protocol identifier advertised by the QUIC client.
+///
+/// The Signal QUIC endpoints speak HTTP/3, which carries the chat WebSocket.
+const ALPN_H3: &[u8] = b"h3";
+
+/// Builds a quinn [`ClientConfig`](quinn::ClientConfig) whose server-certificate verification
+/// matches the WebSocket/TCP route's, honoring every [`RootCertificates`] mode.
+///
+/// The verifier is the same [`ServerCertVerifier`](rustls::client::danger::ServerCertVerifier)
+/// returned by [`RootCertificates::server_cert_verifier`], so SPKI pins, the pinned Signal anchor,
+/// and offline CRL enforcement all apply to QUIC as well. Verification failures are recorded into
+/// `error_slot` exactly as they are for the WebSocket route.
+pub fn client_config(
+    root_certs: &RootCertificates,
+    error_slot: &CertificateErrorSlot,
+) -> Result<quinn::ClientConfig, Error> {
+    let verifier = root_certs.server_cert_verifier(error_slot)?;
+
+    let mut tls_config = rustls::ClientConfig::builder()
+        .dangerous()
+        .with_custom_certificate_verifier(verifier)
+        .with_no_client_auth();
+    tls_config.alpn_protocols = vec![ALPN_H3.to_vec()];
+
+    // QUIC always runs TLS 1.3; `QuicClientConfig::try_from` fails if the config offers no TLS 1.3
+    // cipher suite usable for QUIC's initial packets, which we treat as a certificate-configuration
+    // error.
+    let quic_config = QuicClientConfig::try_from(tls_config).map_err(|_| Error::BadCertificate)?;
+    Ok(quinn::ClientConfig::new(Arc::new(quic_config)))
+}
+
+/// Opens a QUIC connection to `server_addr`, validating the server certificate with the Signal
+/// verifier baked into `config` (see [`client_config`]).
+///
+/// This is the transport primitive the route selector races against the WebSocket/TCP route: it
+/// resolves either to an established [`quinn::Connection`] or to a [`QuicConnectError`] the chat
+/// layer classifies (via `From<QuicConnectError>`) into the same fall-back buckets as the other
+/// routes. `endpoint` is the already-bound client [`quinn::Endpoint`]; `server_name` must match the
+/// certificate's name, as the verifier enforces.
+pub async fn connect(
+    endpoint: &quinn::Endpoint,
+    config: quinn::ClientConfig,
+    server_addr: SocketAddr,
+    server_name: &str,
+) -> Result<quinn::Connection, QuicConnectError> {
+    // `connect_with` fails before any packet leaves the client; awaiting the handshake fails with a
+    // `ConnectionError`. `QuicConnectError`'s `#[from]` arms cover both.
+    let connection = endpoint
+        .connect_with(config, server_addr, server_name)?
+        .await?;
+    Ok(connection)
+}
+
+/// A failure establishing the QUIC transport.
+///
+/// quinn splits connection setup across two error types: [`quinn::ConnectError`] for failures
+/// before any packet leaves the client, and [`quinn::ConnectionError`] for failures of the
+/// handshake or an already-established connection. This collapses both so the chat layer can
+/// classify them uniformly.
+#[derive(Debug, thiserror::Error, displaydoc::Display)]
+pub enum QuicConnectError {
+    /// failed to start QUIC connection: {0}
+    Connect(#[from] quinn::ConnectError),
+    /// QUIC connection failed: {0}
+    Connection(#[from] quinn::ConnectionError),
+}